@@ -94,7 +94,7 @@ includes:
 #![allow(clippy::not_unsafe_ptr_arg_deref)]
 
 use std::cell::RefCell;
-use std::ffi::{c_char, CStr, CString};
+use std::ffi::{c_char, c_void, CStr, CString};
 use std::mem::ManuallyDrop;
 use std::ptr::slice_from_raw_parts_mut;
 use std::slice;
@@ -277,6 +277,7 @@ impl Drop for YRX_PATTERN {
 }
 
 /// Contains information about a pattern match.
+#[derive(Copy, Clone)]
 #[repr(C)]
 pub struct YRX_MATCH {
     /// Offset within the data where the match occurred.
@@ -328,6 +329,307 @@ pub unsafe extern "C" fn yrx_compile(
     }
 }
 
+
+/// Severity of a [`YRX_DIAGNOSTIC`].
+#[repr(C)]
+#[allow(missing_docs)]
+pub enum YRX_DIAGNOSTIC_SEVERITY {
+    ERROR,
+    WARNING,
+}
+
+/// A single error or warning produced while compiling YARA source code.
+#[repr(C)]
+pub struct YRX_DIAGNOSTIC {
+    /// Whether this diagnostic is an error or a warning.
+    pub severity: YRX_DIAGNOSTIC_SEVERITY,
+    /// A short, stable identifier for the kind of problem found (e.g.
+    /// `"syntax_error"`), suitable for matching on in tooling without
+    /// parsing `message`.
+    code: *mut c_char,
+    /// A human-readable description of the problem.
+    message: *mut c_char,
+    /// Byte offset, within the compiled source, where the problem starts.
+    pub span_start: usize,
+    /// Length, in bytes, of the span the problem covers.
+    pub span_length: usize,
+}
+
+impl Drop for YRX_DIAGNOSTIC {
+    fn drop(&mut self) {
+        unsafe {
+            drop(CString::from_raw(self.code));
+            drop(CString::from_raw(self.message));
+        }
+    }
+}
+
+/// The outcome of compiling YARA source code with
+/// [`yrx_compile_with_diagnostics`]: every error and warning produced,
+/// instead of a single formatted string.
+#[repr(C)]
+pub struct YRX_COMPILE_RESULT {
+    /// Number of diagnostics in `diagnostics`.
+    num_diagnostics: usize,
+    /// Pointer to an array of `num_diagnostics` [`YRX_DIAGNOSTIC`]
+    /// structures. If `num_diagnostics` is zero this pointer is invalid and
+    /// should not be de-referenced.
+    diagnostics: *mut YRX_DIAGNOSTIC,
+}
+
+impl Drop for YRX_COMPILE_RESULT {
+    fn drop(&mut self) {
+        unsafe {
+            drop(Box::from_raw(slice_from_raw_parts_mut(
+                self.diagnostics,
+                self.num_diagnostics,
+            )));
+        }
+    }
+}
+
+fn json_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                out.push_str(&format!("\\u{:04x}", c as u32))
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Compiles YARA source code like [`yrx_compile`], but instead of
+/// collapsing every problem found into a single
+/// [`SYNTAX_ERROR`](YRX_RESULT::SYNTAX_ERROR) and a formatted string
+/// retrievable with [`yrx_last_error`], returns every error and warning as
+/// structured data in `compile_result`.
+///
+/// On success, `rules` receives the compiled [`YRX_RULES`]; `compile_result`
+/// may still contain warnings. On failure, `rules` is left untouched and
+/// `compile_result` contains the errors that prevented compilation.
+///
+/// The [`YRX_COMPILE_RESULT`] must be destroyed with
+/// [`yrx_compile_result_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn yrx_compile_with_diagnostics(
+    src: *const c_char,
+    rules: &mut *mut YRX_RULES,
+    compile_result: &mut *mut YRX_COMPILE_RESULT,
+) -> YRX_RESULT {
+    let c_str = CStr::from_ptr(src);
+    let mut diagnostics = Vec::new();
+
+    let result = match c_str.to_str() {
+        Err(_) => {
+            let message = "source is not valid UTF-8".to_string();
+            diagnostics.push(YRX_DIAGNOSTIC {
+                severity: YRX_DIAGNOSTIC_SEVERITY::ERROR,
+                code: CString::new("invalid_utf8").unwrap().into_raw(),
+                message: CString::new(message.clone()).unwrap().into_raw(),
+                span_start: 0,
+                span_length: c_str.to_bytes().len(),
+            });
+            LAST_ERROR.set(Some(CString::new(message).unwrap()));
+            YRX_RESULT::SYNTAX_ERROR
+        }
+        Ok(src_str) => {
+            let mut compiler = yara_x::Compiler::new();
+            match compiler.add_source(src_str) {
+                Ok(_) => {
+                    // Warnings don't prevent compilation, so they're
+                    // reported alongside a successful result. When
+                    // compilation fails, only the error is reported.
+                    for warning in compiler.warnings() {
+                        diagnostics.push(YRX_DIAGNOSTIC {
+                            severity: YRX_DIAGNOSTIC_SEVERITY::WARNING,
+                            code: CString::new(warning.code()).unwrap().into_raw(),
+                            message: CString::new(warning.to_string())
+                                .unwrap()
+                                .into_raw(),
+                            span_start: warning.span().start(),
+                            span_length: warning.span().len(),
+                        });
+                    }
+                    *rules =
+                        Box::into_raw(Box::new(YRX_RULES(compiler.build())));
+                    LAST_ERROR.set(None);
+                    YRX_RESULT::SUCCESS
+                }
+                Err(err) => {
+                    let message = err.to_string();
+                    diagnostics.push(YRX_DIAGNOSTIC {
+                        severity: YRX_DIAGNOSTIC_SEVERITY::ERROR,
+                        code: CString::new(err.code()).unwrap().into_raw(),
+                        message: CString::new(message.clone())
+                            .unwrap()
+                            .into_raw(),
+                        span_start: err.span().start(),
+                        span_length: err.span().len(),
+                    });
+                    LAST_ERROR.set(Some(CString::new(message).unwrap()));
+                    YRX_RESULT::SYNTAX_ERROR
+                }
+            }
+        }
+    };
+
+    let mut diagnostics = ManuallyDrop::new(diagnostics);
+
+    *compile_result = Box::into_raw(Box::new(YRX_COMPILE_RESULT {
+        num_diagnostics: diagnostics.len(),
+        diagnostics: diagnostics.as_mut_ptr(),
+    }));
+
+    result
+}
+
+/// Destroys a [`YRX_COMPILE_RESULT`] object.
+#[no_mangle]
+pub unsafe extern "C" fn yrx_compile_result_destroy(
+    compile_result: *mut YRX_COMPILE_RESULT,
+) {
+    drop(Box::from_raw(compile_result));
+}
+
+/// Serializes a [`YRX_COMPILE_RESULT`]'s diagnostics as a JSON array, with
+/// one object per diagnostic carrying `severity`, `code`, `message`,
+/// `span_start` and `span_length` fields.
+///
+/// The returned [`YRX_BUFFER`] must be destroyed with [`yrx_buffer_destroy`].
+#[no_mangle]
+pub unsafe extern "C" fn yrx_compile_result_as_json(
+    compile_result: *const YRX_COMPILE_RESULT,
+    buf: &mut *mut YRX_BUFFER,
+) -> YRX_RESULT {
+    let compile_result = if let Some(compile_result) = compile_result.as_ref()
+    {
+        compile_result
+    } else {
+        return YRX_RESULT::INVALID_ARGUMENT;
+    };
+
+    let diagnostics = if compile_result.num_diagnostics == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(
+            compile_result.diagnostics,
+            compile_result.num_diagnostics,
+        )
+    };
+
+    let mut json = String::from("[");
+    for (i, d) in diagnostics.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        let severity = match d.severity {
+            YRX_DIAGNOSTIC_SEVERITY::ERROR => "error",
+            YRX_DIAGNOSTIC_SEVERITY::WARNING => "warning",
+        };
+        let code = CStr::from_ptr(d.code).to_string_lossy();
+        let message = CStr::from_ptr(d.message).to_string_lossy();
+        json.push_str(&format!(
+            r#"{{"severity":"{}","code":{},"message":{},"span_start":{},"span_length":{}}}"#,
+            severity,
+            json_escape(&code),
+            json_escape(&message),
+            d.span_start,
+            d.span_length,
+        ));
+    }
+    json.push(']');
+
+    let serialized = json.into_bytes().into_boxed_slice();
+    let mut serialized = ManuallyDrop::new(serialized);
+    *buf = Box::into_raw(Box::new(YRX_BUFFER {
+        data: serialized.as_mut_ptr(),
+        length: serialized.len(),
+    }));
+
+    YRX_RESULT::SUCCESS
+}
+
+/// Callback function passed to [`yrx_rules_serialize_stream`].
+///
+/// Receives a chunk of the serialized rules as `data`/`len`, valid only for
+/// the duration of the call, together with the `user_data` pointer passed
+/// to [`yrx_rules_serialize_stream`]. Returning a nonzero value aborts
+/// serialization.
+pub type YRX_WRITE_CALLBACK = unsafe extern "C" fn(
+    data: *const u8,
+    len: usize,
+    user_data: *mut c_void,
+) -> i32;
+
+/// Adapts a [`YRX_WRITE_CALLBACK`] into a [`std::io::Write`], so that
+/// [`yara_x::Rules::serialize_into`] can write straight to it as it
+/// produces output, rather than into an intermediate, fully-materialized
+/// buffer.
+struct CallbackWriter {
+    callback: YRX_WRITE_CALLBACK,
+    user_data: *mut c_void,
+}
+
+impl std::io::Write for CallbackWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let aborted =
+            unsafe { (self.callback)(buf.as_ptr(), buf.len(), self.user_data) }
+                != 0;
+        if aborted {
+            return Err(std::io::Error::other("aborted by write_callback"));
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the rules like [`yrx_rules_serialize`], but instead of
+/// returning a single, fully-materialized [`YRX_BUFFER`], writes the
+/// serialized bytes to `write_callback` as they're produced. This lets
+/// callers write straight to a file, socket, or compression stream without
+/// an intermediate buffer holding the whole serialized blob at once.
+///
+/// If `write_callback` returns a nonzero value, serialization is aborted
+/// and this function returns [`YRX_RESULT::SERIALIZATION_ERROR`].
+#[no_mangle]
+pub unsafe extern "C" fn yrx_rules_serialize_stream(
+    rules: *const YRX_RULES,
+    write_callback: YRX_WRITE_CALLBACK,
+    user_data: *mut c_void,
+) -> YRX_RESULT {
+    let rules = if let Some(rules) = rules.as_ref() {
+        rules
+    } else {
+        return YRX_RESULT::INVALID_ARGUMENT;
+    };
+
+    let mut writer = CallbackWriter { callback: write_callback, user_data };
+
+    match rules.0.serialize_into(&mut writer) {
+        Ok(()) => {
+            LAST_ERROR.set(None);
+            YRX_RESULT::SUCCESS
+        }
+        Err(err) => {
+            LAST_ERROR.set(Some(CString::new(err.to_string()).unwrap()));
+            YRX_RESULT::SERIALIZATION_ERROR
+        }
+    }
+}
+
 /// Serializes the rules as a sequence of bytes.
 ///
 /// In the address indicated by the `buf` pointer, the function will copy a
@@ -341,26 +643,31 @@ pub unsafe extern "C" fn yrx_rules_serialize(
     rules: *mut YRX_RULES,
     buf: &mut *mut YRX_BUFFER,
 ) -> YRX_RESULT {
-    if let Some(rules) = rules.as_ref() {
-        match rules.0.serialize() {
-            Ok(serialized) => {
-                let serialized = serialized.into_boxed_slice();
-                let mut serialized = ManuallyDrop::new(serialized);
-                *buf = Box::into_raw(Box::new(YRX_BUFFER {
-                    data: serialized.as_mut_ptr(),
-                    length: serialized.len(),
-                }));
-                LAST_ERROR.set(None);
-                YRX_RESULT::SUCCESS
-            }
-            Err(err) => {
-                LAST_ERROR.set(Some(CString::new(err.to_string()).unwrap()));
-                YRX_RESULT::SERIALIZATION_ERROR
-            }
-        }
+    let rules = if let Some(rules) = rules.as_ref() {
+        rules
     } else {
-        YRX_RESULT::INVALID_ARGUMENT
+        return YRX_RESULT::INVALID_ARGUMENT;
+    };
+
+    // `Vec<u8>` implements `Write` directly, so this writes straight into
+    // the buffer that ends up in `YRX_BUFFER` -- no separate callback-based
+    // round trip, and no extra copy.
+    let mut serialized: Vec<u8> = Vec::new();
+
+    if let Err(err) = rules.0.serialize_into(&mut serialized) {
+        LAST_ERROR.set(Some(CString::new(err.to_string()).unwrap()));
+        return YRX_RESULT::SERIALIZATION_ERROR;
     }
+
+    let serialized = serialized.into_boxed_slice();
+    let mut serialized = ManuallyDrop::new(serialized);
+    *buf = Box::into_raw(Box::new(YRX_BUFFER {
+        data: serialized.as_mut_ptr(),
+        length: serialized.len(),
+    }));
+
+    LAST_ERROR.set(None);
+    YRX_RESULT::SUCCESS
 }
 
 /// Deserializes the rules from a sequence of bytes produced by
@@ -526,6 +833,177 @@ pub unsafe extern "C" fn yrx_metadata_destroy(metadata: *mut YRX_METADATA) {
     drop(Box::from_raw(metadata));
 }
 
+/// Callback function passed to [`yrx_rule_iter_patterns`].
+///
+/// The callback receives a pointer to a [`YRX_PATTERN`] that is only valid
+/// for the duration of the call, together with the `user_data` pointer
+/// passed to [`yrx_rule_iter_patterns`]. Its `num_matches`/`matches` fields
+/// are always zero/null: matches are reported separately, through
+/// `match_callback`, as they're produced. Returning `false` stops the
+/// iteration.
+pub type YRX_PATTERN_CALLBACK = unsafe extern "C" fn(
+    pattern: *const YRX_PATTERN,
+    user_data: *mut c_void,
+) -> bool;
+
+/// Callback function passed to [`yrx_rule_iter_patterns`] and
+/// [`yrx_pattern_iter_matches`].
+///
+/// The callback receives a pointer to a [`YRX_MATCH`] that is only valid
+/// for the duration of the call, together with the `user_data` pointer
+/// passed to the calling function. Returning `false` stops the iteration.
+pub type YRX_MATCH_CALLBACK = unsafe extern "C" fn(
+    m: *const YRX_MATCH,
+    user_data: *mut c_void,
+) -> bool;
+
+/// Calls `pattern_callback` once for every pattern defined by a rule, and
+/// `match_callback` once for every match of that pattern, without ever
+/// materializing a pattern's matches into a heap-allocated array.
+///
+/// This is the streaming counterpart of [`yrx_rule_patterns`], meant for
+/// rules with large numbers of matches, where building a [`YRX_PATTERNS`]
+/// object upfront -- or even a single pattern's matches array -- would add
+/// memory proportional to the number of matches. Both callbacks are invoked
+/// with a pointer that's only valid for the duration of the call;
+/// `user_data` is passed through unchanged on every invocation. Returning
+/// `false` from either callback stops the iteration entirely.
+///
+/// This function returns [`YRX_RESULT::INVALID_ARGUMENT`] when `rule` is
+/// null.
+#[no_mangle]
+pub unsafe extern "C" fn yrx_rule_iter_patterns(
+    rule: *const YRX_RULE,
+    pattern_callback: YRX_PATTERN_CALLBACK,
+    match_callback: YRX_MATCH_CALLBACK,
+    user_data: *mut c_void,
+) -> YRX_RESULT {
+    let rule = if let Some(rule) = rule.as_ref() {
+        rule
+    } else {
+        return YRX_RESULT::INVALID_ARGUMENT;
+    };
+
+    'patterns: for pattern in rule.0.patterns() {
+        let identifier = CString::new(pattern.identifier()).unwrap();
+
+        // Wrapped in `ManuallyDrop` because this `YRX_PATTERN` doesn't own
+        // `identifier` (only borrows its bytes) and has no matches array;
+        // running its normal `Drop` impl would double-free `identifier` and
+        // try to free a null `matches` pointer.
+        let yrx_pattern = ManuallyDrop::new(YRX_PATTERN {
+            identifier: identifier.as_ptr() as *mut c_char,
+            num_matches: 0,
+            matches: std::ptr::null_mut(),
+        });
+
+        let keep_going =
+            pattern_callback(&*yrx_pattern as *const YRX_PATTERN, user_data);
+        drop(identifier);
+
+        if !keep_going {
+            break;
+        }
+
+        for m in pattern.matches() {
+            let yrx_match =
+                YRX_MATCH { offset: m.range().start, length: m.range().len() };
+
+            if !match_callback(&yrx_match as *const YRX_MATCH, user_data) {
+                break 'patterns;
+            }
+        }
+    }
+
+    YRX_RESULT::SUCCESS
+}
+
+/// Calls `callback` once for every match already recorded in `pattern`'s
+/// `matches` array.
+///
+/// `pattern` must be one obtained from [`yrx_rule_patterns`] (the pointer
+/// received by a [`yrx_rule_iter_patterns`] callback has no matches array to
+/// iterate; use that function's own `match_callback` instead). `callback`
+/// is invoked with a pointer that's only valid for the duration of the
+/// call.
+///
+/// This function returns [`YRX_RESULT::INVALID_ARGUMENT`] when `pattern` is
+/// null.
+#[no_mangle]
+pub unsafe extern "C" fn yrx_pattern_iter_matches(
+    pattern: *const YRX_PATTERN,
+    callback: YRX_MATCH_CALLBACK,
+    user_data: *mut c_void,
+) -> YRX_RESULT {
+    let pattern = if let Some(pattern) = pattern.as_ref() {
+        pattern
+    } else {
+        return YRX_RESULT::INVALID_ARGUMENT;
+    };
+
+    let matches = if pattern.num_matches == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(pattern.matches, pattern.num_matches)
+    };
+
+    for m in matches {
+        if !callback(m as *const YRX_MATCH, user_data) {
+            break;
+        }
+    }
+
+    YRX_RESULT::SUCCESS
+}
+
+/// Accumulates the patterns and matches streamed by [`yrx_rule_iter_patterns`]
+/// into fully-materialized [`YRX_PATTERN`]s, for [`yrx_rule_patterns`]'s
+/// bulk-snapshot API.
+#[derive(Default)]
+struct PatternsCollector {
+    patterns: Vec<YRX_PATTERN>,
+    current_identifier: Option<CString>,
+    current_matches: Vec<YRX_MATCH>,
+}
+
+impl PatternsCollector {
+    /// Turns the in-progress pattern (if any) into an owned [`YRX_PATTERN`]
+    /// and pushes it into `patterns`.
+    fn finish_current(&mut self) {
+        if let Some(identifier) = self.current_identifier.take() {
+            let matches =
+                std::mem::take(&mut self.current_matches).into_boxed_slice();
+            let mut matches = ManuallyDrop::new(matches);
+
+            self.patterns.push(YRX_PATTERN {
+                identifier: identifier.into_raw(),
+                num_matches: matches.len(),
+                matches: matches.as_mut_ptr(),
+            });
+        }
+    }
+}
+
+unsafe extern "C" fn collect_pattern(
+    pattern: *const YRX_PATTERN,
+    user_data: *mut c_void,
+) -> bool {
+    let collector = &mut *(user_data as *mut PatternsCollector);
+    collector.finish_current();
+    collector.current_identifier =
+        Some(CStr::from_ptr((*pattern).identifier).to_owned());
+    true
+}
+
+unsafe extern "C" fn collect_match(
+    m: *const YRX_MATCH,
+    user_data: *mut c_void,
+) -> bool {
+    let collector = &mut *(user_data as *mut PatternsCollector);
+    collector.current_matches.push(*m);
+    true
+}
+
 /// Returns all the patterns defined by a rule.
 ///
 /// Each pattern contains information about whether it matched or not, and where
@@ -534,42 +1012,28 @@ pub unsafe extern "C" fn yrx_metadata_destroy(metadata: *mut YRX_METADATA) {
 /// anymore.
 ///
 /// This function returns a null pointer when `rule` is null.
+///
+/// This is a convenience wrapper around [`yrx_rule_iter_patterns`] for
+/// callers that prefer a single in-memory snapshot over streaming results.
 #[no_mangle]
 pub unsafe extern "C" fn yrx_rule_patterns(
     rule: *const YRX_RULE,
 ) -> *mut YRX_PATTERNS {
-    let patterns_iter = if let Some(rule) = rule.as_ref() {
-        rule.0.patterns()
-    } else {
+    if rule.is_null() {
         return std::ptr::null_mut();
-    };
-
-    let mut patterns = Vec::with_capacity(patterns_iter.len());
-
-    for pattern in patterns_iter {
-        let matches = pattern
-            .matches()
-            .map(|m| YRX_MATCH {
-                offset: m.range().start,
-                length: m.range().len(),
-            })
-            .collect::<Vec<_>>()
-            .into_boxed_slice();
-
-        // Prevent `matches` from being dropped at the end of the current
-        // scope. We are taking a pointer to `matches` and storing it in a
-        // YRX_PATTERN structure. The `YRX_PATTERN::drop` method takes care
-        // of dropping the slice of matches.
-        let mut matches = ManuallyDrop::new(matches);
-
-        patterns.push(YRX_PATTERN {
-            identifier: CString::new(pattern.identifier()).unwrap().into_raw(),
-            num_matches: matches.len(),
-            matches: matches.as_mut_ptr(),
-        });
     }
 
-    let mut patterns = ManuallyDrop::new(patterns);
+    let mut collector = PatternsCollector::default();
+
+    yrx_rule_iter_patterns(
+        rule,
+        collect_pattern,
+        collect_match,
+        &mut collector as *mut PatternsCollector as *mut c_void,
+    );
+    collector.finish_current();
+
+    let mut patterns = ManuallyDrop::new(collector.patterns);
 
     Box::into_raw(Box::new(YRX_PATTERNS {
         num_patterns: patterns.len(),
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::io::{Read, Seek, SeekFrom};
 
 use nom::multi::fold_many_m_n;
 use nom::{
@@ -17,14 +18,22 @@ const MINI_SECTOR_SHIFT: u16 = 6;
 const DIRECTORY_ENTRY_SIZE: u64 = 128;
 
 // Directory Entry Types
+const UNKNOWN_OR_UNALLOCATED_TYPE: u8 = 0;
 const STORAGE_TYPE: u8 = 1;
 const STREAM_TYPE: u8 = 2;
 const ROOT_STORAGE_TYPE: u8 = 5;
 
 // Special sectors
 const ENDOFCHAIN: u32 = 0xFFFFFFFE;
+const FREESECT: u32 = 0xFFFFFFFF;
 const MAX_REGULAR_SECTOR: u32 = 0xFFFFFFFA;
 
+// Sentinel value used in the `left_sibling_id`/`right_sibling_id`/
+// `child_id` fields of a directory entry to mean "no such node". It has
+// the same numeric value as `FREESECT`, but a distinct name since it's
+// used in a different context ([MS-CFB] 2.6.1 calls it `NOSTREAM`).
+const NOSTREAM: u32 = 0xFFFFFFFF;
+
 pub struct OLECFParser<'a> {
     data: &'a [u8],
     sector_size: usize,
@@ -32,16 +41,32 @@ pub struct OLECFParser<'a> {
     fat_sectors: Vec<u32>,
     directory_sectors: Vec<u32>,
     mini_fat_sectors: Vec<u32>,
+    /// All directory entries, indexed by their position in the directory
+    /// entry array (i.e. their `child_id`/`left_sibling_id`/
+    /// `right_sibling_id`). Includes unused/unallocated slots so that IDs
+    /// line up with the ones referenced by the red-black tree.
+    entries: Vec<DirectoryEntry>,
+    /// Flat, name-keyed view of `entries`, kept for backward compatibility
+    /// with callers that don't care about the storage hierarchy. As in the
+    /// original implementation, a name shared by entries in different
+    /// storages resolves to whichever one was seen last.
     dir_entries: HashMap<String, DirectoryEntry>,
+    /// Full storage/stream path (e.g. `Macros/VBA/ThisDocument`) to the
+    /// entry's ID in `entries`.
+    paths: HashMap<String, u32>,
     mini_stream_start: u32,
     mini_stream_size: u64,
 }
 
+#[derive(Clone)]
 pub struct DirectoryEntry {
     pub name: String,
     pub size: u64,
     pub start_sector: u32,
     pub stream_type: u8,
+    pub left_sibling_id: u32,
+    pub right_sibling_id: u32,
+    pub child_id: u32,
 }
 
 impl<'a> OLECFParser<'a> {
@@ -53,7 +78,9 @@ impl<'a> OLECFParser<'a> {
             fat_sectors: Vec::new(),
             directory_sectors: Vec::new(),
             mini_fat_sectors: Vec::new(),
+            entries: Vec::new(),
             dir_entries: HashMap::new(),
+            paths: HashMap::new(),
             mini_stream_start: 0,
             mini_stream_size: 0,
         };
@@ -81,8 +108,8 @@ impl<'a> OLECFParser<'a> {
                 _minor_version,
                 _major_version,
                 _byte_order,
-                _sector_shift,
-                _mini_sector_shift,
+                sector_shift,
+                mini_sector_shift,
                 _reserved,
                 _num_dir_sectors,
                 num_fat_sectors,
@@ -91,7 +118,7 @@ impl<'a> OLECFParser<'a> {
                 _mini_stream_cutoff_size,
                 first_mini_fat,
                 mini_fat_count,
-                _first_difat_sector,
+                first_difat_sector,
                 _difat_count,
             ),
         ) = tuple((
@@ -114,6 +141,20 @@ impl<'a> OLECFParser<'a> {
             le_u32,       // _difat_count
         ))(input)?;
 
+        // Validate and apply the sector sizes declared by the header.
+        // Version-3 files use 512-byte sectors (sector_shift == 9), while
+        // version-4 files use 4096-byte sectors (sector_shift == 12); the
+        // mini sector size is always 64 bytes (mini_sector_shift == 6).
+        if (sector_shift != 9 && sector_shift != 12) || mini_sector_shift != 6
+        {
+            return Err(nom::Err::Error(NomError::new(
+                input,
+                ErrorKind::Verify,
+            )));
+        }
+        self.sector_size = 1usize << sector_shift;
+        self.mini_sector_size = 1usize << mini_sector_shift;
+
         // Parse the first 109 DIFAT entries, which are contained in the
         // header sector.
         let (input, _) = fold_many_m_n(
@@ -128,6 +169,14 @@ impl<'a> OLECFParser<'a> {
             },
         )(input)?;
 
+        // Large compound files list the remaining FAT sectors in a chain
+        // of DIFAT sectors, starting at `first_difat_sector`. Without this,
+        // files with more than 109 FAT sectors would be missing most of
+        // their FAT and fail to parse.
+        if first_difat_sector < MAX_REGULAR_SECTOR {
+            self.follow_difat_chain(first_difat_sector);
+        }
+
         // (C) Directory chain
         if first_dir_sector < MAX_REGULAR_SECTOR {
             self.directory_sectors = self.follow_chain(first_dir_sector);
@@ -162,6 +211,8 @@ impl<'a> OLECFParser<'a> {
             )));
         }
 
+        let mut root_child_id = None;
+
         for &sector in &self.directory_sectors {
             let mut entry_offset = 0;
 
@@ -173,25 +224,114 @@ impl<'a> OLECFParser<'a> {
                 {
                     break;
                 }
-                if let Ok(entry) = self.read_directory_entry(abs_offset) {
-                    if entry.stream_type == ROOT_STORAGE_TYPE {
-                        self.mini_stream_start = entry.start_sector;
-                        self.mini_stream_size = entry.size;
-                    }
-                    if entry.stream_type == STORAGE_TYPE
-                        || entry.stream_type == STREAM_TYPE
-                        || entry.stream_type == ROOT_STORAGE_TYPE
-                    {
-                        self.dir_entries.insert(entry.name.clone(), entry);
-                    }
+
+                // Every 128-byte slot becomes an entry, even unused ones,
+                // so that IDs line up with the ones referenced by the
+                // red-black tree's `left_sibling_id`/`right_sibling_id`/
+                // `child_id` fields.
+                let entry = self
+                    .read_directory_entry(abs_offset)
+                    .unwrap_or_else(|_| DirectoryEntry {
+                        name: String::new(),
+                        size: 0,
+                        start_sector: FREESECT,
+                        stream_type: UNKNOWN_OR_UNALLOCATED_TYPE,
+                        left_sibling_id: NOSTREAM,
+                        right_sibling_id: NOSTREAM,
+                        child_id: NOSTREAM,
+                    });
+
+                if entry.stream_type == ROOT_STORAGE_TYPE {
+                    self.mini_stream_start = entry.start_sector;
+                    self.mini_stream_size = entry.size;
+                    root_child_id = Some(entry.child_id);
+                }
+                if entry.stream_type == STORAGE_TYPE
+                    || entry.stream_type == STREAM_TYPE
+                    || entry.stream_type == ROOT_STORAGE_TYPE
+                {
+                    self.dir_entries.insert(entry.name.clone(), entry.clone());
                 }
+
+                self.entries.push(entry);
                 entry_offset += DIRECTORY_ENTRY_SIZE as usize;
             }
         }
 
+        // The directory entries form a red-black tree: the root storage's
+        // `child_id` is the root of that tree, and every storage's own
+        // `child_id` is the root of the tree of entries it directly
+        // contains. Walking it (rather than just flattening entries by
+        // name) is what lets us recover full paths like
+        // `Macros/VBA/ThisDocument`.
+        if let Some(root_child_id) = root_child_id {
+            let mut visited = Vec::new();
+            self.walk_storage_tree(root_child_id, "", &mut visited);
+        }
+
         Ok((_input, ()))
     }
 
+    /// Walks the red-black tree of sibling entries rooted at `node_id`,
+    /// which are all direct children of the same storage, recording full
+    /// paths (`prefix` joined with each entry's name) in `self.paths`.
+    /// Storages are descended into using their own `child_id`. `visited`
+    /// guards against cycles in a malformed tree.
+    ///
+    /// This uses an explicit work stack rather than recursing: a
+    /// directory with a long (but acyclic) chain of sibling or nested
+    /// storage entries would otherwise be able to blow the call stack.
+    fn walk_storage_tree(
+        &mut self,
+        node_id: u32,
+        prefix: &str,
+        visited: &mut Vec<u32>,
+    ) {
+        let mut stack = vec![(node_id, prefix.to_string())];
+
+        while let Some((node_id, prefix)) = stack.pop() {
+            if node_id == NOSTREAM || node_id as usize >= self.entries.len() {
+                continue;
+            }
+            if visited.contains(&node_id) {
+                continue;
+            }
+            visited.push(node_id);
+
+            let (left, right, child, stream_type, name) = {
+                let entry = &self.entries[node_id as usize];
+                (
+                    entry.left_sibling_id,
+                    entry.right_sibling_id,
+                    entry.child_id,
+                    entry.stream_type,
+                    entry.name.clone(),
+                )
+            };
+
+            let path = if prefix.is_empty() {
+                name
+            } else {
+                format!("{prefix}/{name}")
+            };
+
+            if stream_type == STORAGE_TYPE || stream_type == STREAM_TYPE {
+                self.paths.insert(path.clone(), node_id);
+            }
+
+            // Pushed in reverse of visit order: the stack pops `left`
+            // first, so it (and everything under it) is fully processed
+            // before `child`, which in turn finishes before `right`. Each
+            // node is thus handled before its left subtree, then its
+            // child subtree, then its right subtree.
+            stack.push((right, prefix.clone()));
+            if stream_type == STORAGE_TYPE {
+                stack.push((child, path));
+            }
+            stack.push((left, prefix));
+        }
+    }
+
     pub fn is_valid_header(&self) -> bool {
         self.data.len() >= OLECF_SIGNATURE.len()
             && &self.data[..OLECF_SIGNATURE.len()] == OLECF_SIGNATURE
@@ -226,7 +366,66 @@ impl<'a> OLECFParser<'a> {
     ) -> Result<Vec<u8>, &'static str> {
         let entry =
             self.dir_entries.get(stream_name).ok_or("Stream not found")?;
+        self.read_entry_data(entry)
+    }
+
+    /// Returns the decompressed contents of `stream_name`, a stream
+    /// compressed with the MS-OVBA "Compression" algorithm (as used by VBA
+    /// macro streams, e.g. the `dir` or module streams inside a `VBA`
+    /// storage). See [`decompress_ovba`].
+    pub fn get_decompressed_stream_data(
+        &self,
+        stream_name: &str,
+    ) -> Result<Vec<u8>, &'static str> {
+        decompress_ovba(&self.get_stream_data(stream_name)?)
+    }
+
+    /// Returns every storage and stream in the compound file, keyed by its
+    /// full path (e.g. `Macros/VBA/ThisDocument`), as reconstructed from
+    /// the directory entries' red-black tree. Unlike [`Self::get_streams`],
+    /// two entries with the same name under different storages don't
+    /// collide here.
+    pub fn get_streams_by_path(
+        &self,
+    ) -> impl Iterator<Item = (&str, &DirectoryEntry)> {
+        self.paths
+            .iter()
+            .map(|(path, &id)| (path.as_str(), &self.entries[id as usize]))
+    }
+
+    /// Returns the data of the stream at `path` (as returned by
+    /// [`Self::get_streams_by_path`]).
+    pub fn get_stream_data_by_path(
+        &self,
+        path: &str,
+    ) -> Result<Vec<u8>, &'static str> {
+        let &id = self.paths.get(path).ok_or("Stream not found")?;
+        self.read_entry_data(&self.entries[id as usize])
+    }
+
+    /// Lists the direct children of the storage at `storage_path`, keyed by
+    /// their full path. Pass an empty string for the entries directly
+    /// under the root storage.
+    pub fn get_children(
+        &self,
+        storage_path: &str,
+    ) -> impl Iterator<Item = (&str, &DirectoryEntry)> {
+        let prefix = if storage_path.is_empty() {
+            String::new()
+        } else {
+            format!("{storage_path}/")
+        };
+        self.paths.iter().filter_map(move |(path, &id)| {
+            let rest = path.strip_prefix(prefix.as_str())?;
+            (!rest.is_empty() && !rest.contains('/'))
+                .then(|| (path.as_str(), &self.entries[id as usize]))
+        })
+    }
 
+    fn read_entry_data(
+        &self,
+        entry: &DirectoryEntry,
+    ) -> Result<Vec<u8>, &'static str> {
         if entry.size < 4096 && entry.stream_type != ROOT_STORAGE_TYPE {
             self.get_mini_stream_data(entry.start_sector, entry.size)
         } else {
@@ -235,8 +434,11 @@ impl<'a> OLECFParser<'a> {
     }
 
     fn sector_to_offset(&self, sector: u32) -> usize {
-        // The first sector begins at offset 512
-        512 + (sector as usize * self.sector_size)
+        // The header always occupies exactly one sector, so sector 0's
+        // data begins right after it: at offset 512 for version-3 files
+        // (512-byte sectors) and at offset 4096 for version-4 files
+        // (4096-byte sectors), rather than a hard-coded 512.
+        self.sector_size + (sector as usize * self.sector_size)
     }
 
     fn read_sector(&self, sector: u32) -> Result<&[u8], &'static str> {
@@ -289,30 +491,53 @@ impl<'a> OLECFParser<'a> {
         chain
     }
 
-    fn read_directory_entry(
-        &self,
-        offset: usize,
-    ) -> Result<DirectoryEntry, &'static str> {
-        if offset + 128 > self.data.len() {
-            return Err("Incomplete directory entry");
-        }
+    /// Walks the DIFAT chain starting at `first_difat_sector`, pushing
+    /// every FAT sector ID it lists into `fat_sectors`.
+    ///
+    /// Each DIFAT sector holds `sector_size/4 - 1` FAT sector IDs, followed
+    /// by a final `u32` pointing to the next DIFAT sector in the chain.
+    /// The chain ends at `ENDOFCHAIN`/`FREESECT`.
+    fn follow_difat_chain(&mut self, first_difat_sector: u32) {
+        let entries_per_sector = self.sector_size / 4 - 1;
+        let mut visited = Vec::new();
+        let mut current = first_difat_sector;
 
-        let name_len = parse_u16_at(self.data, offset + 64)? as usize;
-        if !(2..=64).contains(&name_len) {
-            return Err("Invalid name length");
-        }
+        while current < MAX_REGULAR_SECTOR {
+            // Prevent cycles by keeping track of visited sectors.
+            if visited.contains(&current) {
+                break;
+            }
+            visited.push(current);
+
+            let sector = match self.read_sector(current) {
+                Ok(sector) => sector,
+                Err(_) => break,
+            };
+
+            for i in 0..entries_per_sector {
+                if let Ok(fat_sector) = parse_u32_at(sector, i * 4) {
+                    if fat_sector < MAX_REGULAR_SECTOR {
+                        self.fat_sectors.push(fat_sector);
+                    }
+                }
+            }
 
-        let name_bytes = &self.data[offset..offset + name_len];
-        let filtered: Vec<u8> =
-            name_bytes.iter().copied().filter(|&b| b != 0).collect();
-        let name = String::from_utf8_lossy(&filtered).to_string();
+            current = match parse_u32_at(sector, entries_per_sector * 4) {
+                Ok(next) => next,
+                Err(_) => break,
+            };
 
-        let stream_type = self.data[offset + 66];
-        let start_sector = parse_u32_at(self.data, offset + 116)?;
-        let size_32 = parse_u32_at(self.data, offset + 120)?;
-        let size = size_32 as u64;
+            if current == ENDOFCHAIN || current == FREESECT {
+                break;
+            }
+        }
+    }
 
-        Ok(DirectoryEntry { name, size, start_sector, stream_type })
+    fn read_directory_entry(
+        &self,
+        offset: usize,
+    ) -> Result<DirectoryEntry, &'static str> {
+        parse_directory_entry(self.data, offset)
     }
 
     fn get_regular_stream_data(
@@ -320,29 +545,28 @@ impl<'a> OLECFParser<'a> {
         start_sector: u32,
         size: u64,
     ) -> Result<Vec<u8>, &'static str> {
-        let mut data = Vec::with_capacity(size as usize);
-        let mut current_sector = start_sector;
-        let mut total_read = 0;
-
-        while current_sector < MAX_REGULAR_SECTOR && total_read < size as usize
-        {
-            let sector_data = self.read_sector(current_sector)?;
-            let bytes_to_read =
-                std::cmp::min(self.sector_size, size as usize - total_read);
+        let chain = self.follow_chain(start_sector);
 
-            data.extend_from_slice(&sector_data[..bytes_to_read]);
-            total_read += bytes_to_read;
+        // Clamp the declared size against what's actually reachable
+        // through the FAT chain, so a crafted directory entry claiming a
+        // multi-gigabyte stream can't force an allocation far larger than
+        // the file actually is.
+        let reachable = chain.len() as u64 * self.sector_size as u64;
+        let target = std::cmp::min(size, reachable) as usize;
+        let mut remaining = target;
 
-            if total_read < size as usize {
-                let next = self.get_fat_entry(current_sector)?;
-                if next == ENDOFCHAIN || next >= MAX_REGULAR_SECTOR {
-                    break;
-                }
-                current_sector = next;
+        let mut data = Vec::with_capacity(remaining);
+        for sector in chain {
+            if remaining == 0 {
+                break;
             }
+            let sector_data = self.read_sector(sector)?;
+            let n = std::cmp::min(self.sector_size, remaining);
+            data.extend_from_slice(&sector_data[..n]);
+            remaining -= n;
         }
 
-        if data.len() != size as usize {
+        if data.len() != target {
             return Err("Incomplete stream data");
         }
 
@@ -388,37 +612,46 @@ impl<'a> OLECFParser<'a> {
         let mini_stream_data = self.get_root_mini_stream_data()?;
         let mini_data_len = mini_stream_data.len();
 
-        let mut data = Vec::with_capacity(size as usize);
+        let mut chain = Vec::new();
         let mut current = start_mini_sector;
+        while current < MAX_REGULAR_SECTOR {
+            if chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+            current = match self.get_minifat_entry(current) {
+                Ok(n) if n == ENDOFCHAIN => break,
+                Ok(n) => n,
+                Err(_) => break,
+            };
+        }
+
+        // Same clamping as `get_regular_stream_data`, but against mini
+        // sectors reachable within the already-bounded mini stream.
+        let reachable = chain.len() as u64 * self.mini_sector_size as u64;
+        let target = std::cmp::min(size, reachable) as usize;
+        let mut remaining = target;
 
-        while current < MAX_REGULAR_SECTOR && data.len() < size as usize {
-            let mini_offset = current as usize * self.mini_sector_size;
+        let mut data = Vec::with_capacity(remaining);
+        for mini_sector in chain {
+            if remaining == 0 {
+                break;
+            }
+            let mini_offset = mini_sector as usize * self.mini_sector_size;
             if mini_offset >= mini_data_len {
                 return Err("Mini stream offset out of range");
             }
-
-            let bytes_to_read = std::cmp::min(
-                self.mini_sector_size,
-                size as usize - data.len(),
+            let n = std::cmp::min(
+                std::cmp::min(self.mini_sector_size, remaining),
+                mini_data_len - mini_offset,
             );
-            if mini_offset + bytes_to_read > mini_data_len {
-                return Err("Mini stream extends beyond available data");
-            }
-
             data.extend_from_slice(
-                &mini_stream_data[mini_offset..mini_offset + bytes_to_read],
+                &mini_stream_data[mini_offset..mini_offset + n],
             );
-
-            if data.len() < size as usize {
-                let next = self.get_minifat_entry(current)?;
-                if next == ENDOFCHAIN || next >= MAX_REGULAR_SECTOR {
-                    break;
-                }
-                current = next;
-            }
+            remaining -= n;
         }
 
-        if data.len() != size as usize {
+        if data.len() != target {
             return Err("Incomplete mini stream data");
         }
 
@@ -426,6 +659,57 @@ impl<'a> OLECFParser<'a> {
     }
 }
 
+/// Parses the 128-byte directory entry located at `offset` within `data`.
+/// Shared by [`OLECFParser`] (which keeps the whole file in memory) and
+/// [`OLECFReader`] (which parses one sector at a time from a `Read + Seek`
+/// source).
+fn parse_directory_entry(
+    data: &[u8],
+    offset: usize,
+) -> Result<DirectoryEntry, &'static str> {
+    if offset + 128 > data.len() {
+        return Err("Incomplete directory entry");
+    }
+
+    let name_len = parse_u16_at(data, offset + 64)? as usize;
+    if !(2..=64).contains(&name_len) {
+        return Err("Invalid name length");
+    }
+
+    // [MS-CFB] 2.6.1: `name_len` counts bytes and includes the 2-byte
+    // NUL terminator, so the name itself is the first `name_len - 2`
+    // bytes, encoded as UTF-16LE. Decoding it properly (rather than
+    // just stripping NUL bytes and treating the rest as UTF-8) is what
+    // lets names with non-ASCII characters, and the leading control
+    // byte of special stream names like `\x05SummaryInformation`,
+    // come through intact.
+    let payload_len = name_len - 2;
+    let name_bytes = &data[offset..offset + payload_len];
+    let units: Vec<u16> = name_bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let name = String::from_utf16_lossy(&units);
+
+    let stream_type = data[offset + 66];
+    let left_sibling_id = parse_u32_at(data, offset + 68)?;
+    let right_sibling_id = parse_u32_at(data, offset + 72)?;
+    let child_id = parse_u32_at(data, offset + 76)?;
+    let start_sector = parse_u32_at(data, offset + 116)?;
+    let size_32 = parse_u32_at(data, offset + 120)?;
+    let size = size_32 as u64;
+
+    Ok(DirectoryEntry {
+        name,
+        size,
+        start_sector,
+        stream_type,
+        left_sibling_id,
+        right_sibling_id,
+        child_id,
+    })
+}
+
 fn parse_u16_at(data: &[u8], offset: usize) -> Result<u16, &'static str> {
     if offset + 2 > data.len() {
         return Err("Buffer too small for u16");
@@ -447,3 +731,662 @@ fn parse_u32_at(data: &[u8], offset: usize) -> Result<u32, &'static str> {
         Err(_) => Err("Failed to parse u32"),
     }
 }
+
+/// Decompresses a byte stream compressed with the MS-OVBA "Compression"
+/// algorithm (MS-OVBA 2.4.1), as used by VBA macro streams.
+///
+/// `data` must start with the 0x01 signature byte, followed by a sequence
+/// of chunks. Each chunk is either 4096 bytes of literal data, or a
+/// compressed chunk decompressing to at most 4096 bytes.
+pub fn decompress_ovba(data: &[u8]) -> Result<Vec<u8>, &'static str> {
+    if data.first() != Some(&0x01) {
+        return Err("Invalid OVBA signature");
+    }
+
+    let mut out = Vec::new();
+    let mut pos = 1;
+
+    while pos + 2 <= data.len() {
+        let header = u16::from_le_bytes([data[pos], data[pos + 1]]);
+        pos += 2;
+
+        // Bits 12-14 of the header are always 0b011.
+        if (header >> 12) & 0x7 != 0b011 {
+            return Err("Invalid OVBA chunk header");
+        }
+
+        let compressed = header & 0x8000 != 0;
+        let chunk_size = (header & 0x0FFF) as usize + 3;
+
+        // `chunk_size` includes the 2-byte header itself.
+        let body_len = chunk_size.checked_sub(2).ok_or("Invalid OVBA chunk size")?;
+        if pos + body_len > data.len() {
+            return Err("Truncated OVBA chunk");
+        }
+        let body = &data[pos..pos + body_len];
+        pos += body_len;
+
+        if !compressed {
+            out.extend_from_slice(body);
+            continue;
+        }
+
+        // The offset, within `out`, where this chunk's decompressed data
+        // starts. Copy tokens can only reference bytes produced by the
+        // same chunk.
+        let chunk_start = out.len();
+        let mut i = 0;
+
+        while i < body.len() {
+            let flags = body[i];
+            i += 1;
+
+            for bit in 0..8 {
+                if i >= body.len() {
+                    break;
+                }
+                if flags & (1 << bit) == 0 {
+                    // Literal token: copy a single byte verbatim.
+                    out.push(body[i]);
+                    i += 1;
+                } else {
+                    // Copy token: a 2-byte, little-endian (offset, length)
+                    // pair, packed according to how much data has been
+                    // decompressed so far within this chunk.
+                    if i + 2 > body.len() {
+                        return Err("Truncated OVBA copy token");
+                    }
+                    let token = u16::from_le_bytes([body[i], body[i + 1]]);
+                    i += 2;
+
+                    let difference = out.len() - chunk_start;
+                    let bit_count = ovba_copy_token_bit_count(difference);
+                    let length =
+                        (token & (0xFFFF >> bit_count)) as usize + 3;
+                    let offset =
+                        (token >> (16 - bit_count)) as usize + 1;
+
+                    if offset > out.len() {
+                        return Err("Invalid OVBA copy-token offset");
+                    }
+
+                    // Copy byte by byte, rather than via `extend_from_within`,
+                    // because source and destination ranges can overlap
+                    // (a copy token is allowed to reference bytes it is
+                    // itself in the process of producing).
+                    let mut src = out.len() - offset;
+                    for _ in 0..length {
+                        let byte = out[src];
+                        out.push(byte);
+                        src += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Number of bits used for the length field of an MS-OVBA copy token,
+/// given how many bytes have been decompressed so far within the current
+/// chunk (MS-OVBA 2.4.1.3.19): `max(ceil(log2(difference)), 4)`, clamped
+/// to 12.
+fn ovba_copy_token_bit_count(difference: usize) -> u32 {
+    let mut bit_count = 4u32;
+    while (1usize << bit_count) < difference {
+        bit_count += 1;
+    }
+    bit_count.min(12)
+}
+
+/// A compound file parser that reads from any `R: Read + Seek` instead of
+/// requiring the whole file in memory like [`OLECFParser`].
+///
+/// Only the header, FAT, MiniFAT and directory are parsed upfront; a
+/// stream's data is read on demand, sector by sector, by
+/// [`OLECFReader::get_stream_data`]. A stream's declared size is clamped
+/// against the number of sectors actually reachable through its FAT (or
+/// MiniFAT) chain before allocating, so a crafted directory entry claiming
+/// a multi-gigabyte stream can't be used to trigger an OOM.
+pub struct OLECFReader<R> {
+    reader: R,
+    sector_size: usize,
+    mini_sector_size: usize,
+    fat_sectors: Vec<u32>,
+    mini_fat_sectors: Vec<u32>,
+    dir_entries: HashMap<String, DirectoryEntry>,
+    mini_stream_start: u32,
+    mini_stream_size: u64,
+}
+
+impl<R: Read + Seek> OLECFReader<R> {
+    /// Parses the header, FAT, MiniFAT and directory of the compound file
+    /// in `reader`, without reading the data of any individual stream.
+    pub fn new(mut reader: R) -> Result<Self, &'static str> {
+        let mut header = [0u8; 512];
+        reader
+            .read_exact(&mut header)
+            .map_err(|_| "Failed to read CFB header")?;
+
+        if &header[0..OLECF_SIGNATURE.len()] != OLECF_SIGNATURE {
+            return Err("Invalid OLECF signature");
+        }
+        if u16::from_le_bytes([header[28], header[29]]) != 0xFFFE {
+            return Err("Invalid byte order mark");
+        }
+
+        let sector_shift = u16::from_le_bytes([header[30], header[31]]);
+        let mini_sector_shift = u16::from_le_bytes([header[32], header[33]]);
+        if (sector_shift != 9 && sector_shift != 12)
+            || mini_sector_shift != 6
+        {
+            return Err("Unsupported sector shift");
+        }
+
+        let num_fat_sectors = u32::from_le_bytes(
+            header[44..48].try_into().unwrap(),
+        );
+        let first_dir_sector = u32::from_le_bytes(
+            header[48..52].try_into().unwrap(),
+        );
+        let first_mini_fat = u32::from_le_bytes(
+            header[60..64].try_into().unwrap(),
+        );
+        let mini_fat_count = u32::from_le_bytes(
+            header[64..68].try_into().unwrap(),
+        );
+        let first_difat_sector = u32::from_le_bytes(
+            header[68..72].try_into().unwrap(),
+        );
+
+        let mut parser = OLECFReader {
+            reader,
+            sector_size: 1usize << sector_shift,
+            mini_sector_size: 1usize << mini_sector_shift,
+            fat_sectors: Vec::new(),
+            mini_fat_sectors: Vec::new(),
+            dir_entries: HashMap::new(),
+            mini_stream_start: 0,
+            mini_stream_size: 0,
+        };
+
+        for i in 0..109 {
+            let entry_offset = 76 + i * 4;
+            let sector = u32::from_le_bytes(
+                header[entry_offset..entry_offset + 4].try_into().unwrap(),
+            );
+            if sector < MAX_REGULAR_SECTOR {
+                parser.fat_sectors.push(sector);
+            }
+        }
+
+        if first_difat_sector < MAX_REGULAR_SECTOR {
+            parser.follow_difat_chain(first_difat_sector)?;
+        }
+
+        if parser.fat_sectors.is_empty() && num_fat_sectors > 0 {
+            return Err("Missing FAT sectors");
+        }
+
+        if first_dir_sector >= MAX_REGULAR_SECTOR {
+            return Err("Missing directory sector");
+        }
+        let directory_sectors = parser.follow_chain(first_dir_sector)?;
+
+        if mini_fat_count > 0 && first_mini_fat < MAX_REGULAR_SECTOR {
+            parser.mini_fat_sectors = parser.follow_chain(first_mini_fat)?;
+        }
+
+        parser.parse_directory(&directory_sectors)?;
+
+        Ok(parser)
+    }
+
+    /// Names of every stream and storage found in the directory.
+    pub fn get_stream_names(&self) -> Vec<String> {
+        self.dir_entries.keys().cloned().collect()
+    }
+
+    /// Declared size, in bytes, of `stream_name`.
+    pub fn get_stream_size(
+        &self,
+        stream_name: &str,
+    ) -> Result<u64, &'static str> {
+        self.dir_entries
+            .get(stream_name)
+            .map(|e| e.size)
+            .ok_or("Stream not found")
+    }
+
+    /// Reads the data of `stream_name`, one sector at a time, from the
+    /// underlying reader.
+    pub fn get_stream_data(
+        &mut self,
+        stream_name: &str,
+    ) -> Result<Vec<u8>, &'static str> {
+        let entry = self
+            .dir_entries
+            .get(stream_name)
+            .cloned()
+            .ok_or("Stream not found")?;
+
+        if entry.size < 4096 && entry.stream_type != ROOT_STORAGE_TYPE {
+            self.get_mini_stream_data(entry.start_sector, entry.size)
+        } else {
+            self.get_regular_stream_data(entry.start_sector, entry.size)
+        }
+    }
+
+    fn read_sector(&mut self, sector: u32) -> Result<Vec<u8>, &'static str> {
+        let offset = self.sector_size + sector as usize * self.sector_size;
+        self.reader
+            .seek(SeekFrom::Start(offset as u64))
+            .map_err(|_| "Sector seek out of bounds")?;
+        let mut buf = vec![0u8; self.sector_size];
+        self.reader
+            .read_exact(&mut buf)
+            .map_err(|_| "Sector read out of bounds")?;
+        Ok(buf)
+    }
+
+    fn get_fat_entry(&mut self, sector: u32) -> Result<u32, &'static str> {
+        let entries_per_sector = self.sector_size / 4;
+        let fat_sector_index = sector as usize / entries_per_sector;
+        let fat_sector = *self
+            .fat_sectors
+            .get(fat_sector_index)
+            .ok_or("FAT entry sector index out of range")?;
+        let fat = self.read_sector(fat_sector)?;
+        let entry_offset = (sector as usize % entries_per_sector) * 4;
+        Ok(u32::from_le_bytes(
+            fat[entry_offset..entry_offset + 4].try_into().unwrap(),
+        ))
+    }
+
+    /// Follows a FAT chain starting at `start_sector`, guarding against
+    /// cycles the same way [`OLECFParser::follow_chain`] does.
+    fn follow_chain(
+        &mut self,
+        start_sector: u32,
+    ) -> Result<Vec<u32>, &'static str> {
+        let mut chain = Vec::new();
+        let mut current = start_sector;
+
+        while current < MAX_REGULAR_SECTOR {
+            if chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+            current = match self.get_fat_entry(current) {
+                Ok(n) if n == ENDOFCHAIN => break,
+                Ok(n) => n,
+                Err(_) => break,
+            };
+        }
+
+        Ok(chain)
+    }
+
+    fn follow_difat_chain(
+        &mut self,
+        first_difat_sector: u32,
+    ) -> Result<(), &'static str> {
+        let entries_per_sector = self.sector_size / 4 - 1;
+        let mut visited = Vec::new();
+        let mut current = first_difat_sector;
+
+        while current < MAX_REGULAR_SECTOR {
+            if visited.contains(&current) {
+                break;
+            }
+            visited.push(current);
+
+            let sector = self.read_sector(current)?;
+            for i in 0..entries_per_sector {
+                let fat_sector = u32::from_le_bytes(
+                    sector[i * 4..i * 4 + 4].try_into().unwrap(),
+                );
+                if fat_sector < MAX_REGULAR_SECTOR {
+                    self.fat_sectors.push(fat_sector);
+                }
+            }
+
+            let next = u32::from_le_bytes(
+                sector[entries_per_sector * 4..entries_per_sector * 4 + 4]
+                    .try_into()
+                    .unwrap(),
+            );
+            if next == ENDOFCHAIN || next == FREESECT {
+                break;
+            }
+            current = next;
+        }
+
+        Ok(())
+    }
+
+    fn parse_directory(
+        &mut self,
+        directory_sectors: &[u32],
+    ) -> Result<(), &'static str> {
+        for &sector in directory_sectors {
+            let data = self.read_sector(sector)?;
+            let mut entry_offset = 0;
+
+            while entry_offset + DIRECTORY_ENTRY_SIZE as usize <= data.len() {
+                if let Ok(entry) =
+                    parse_directory_entry(&data, entry_offset)
+                {
+                    if entry.stream_type == ROOT_STORAGE_TYPE {
+                        self.mini_stream_start = entry.start_sector;
+                        self.mini_stream_size = entry.size;
+                    }
+                    if entry.stream_type == STORAGE_TYPE
+                        || entry.stream_type == STREAM_TYPE
+                        || entry.stream_type == ROOT_STORAGE_TYPE
+                    {
+                        self.dir_entries.insert(entry.name.clone(), entry);
+                    }
+                }
+                entry_offset += DIRECTORY_ENTRY_SIZE as usize;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn get_regular_stream_data(
+        &mut self,
+        start_sector: u32,
+        size: u64,
+    ) -> Result<Vec<u8>, &'static str> {
+        let chain = self.follow_chain(start_sector)?;
+
+        // Clamp the declared size against what's actually reachable
+        // through the FAT chain, so a bogus size in a crafted directory
+        // entry can't force an allocation far larger than the file.
+        let reachable = chain.len() as u64 * self.sector_size as u64;
+        let mut remaining = std::cmp::min(size, reachable) as usize;
+
+        let mut data = Vec::with_capacity(remaining);
+        for sector in chain {
+            if remaining == 0 {
+                break;
+            }
+            let sector_data = self.read_sector(sector)?;
+            let n = std::cmp::min(self.sector_size, remaining);
+            data.extend_from_slice(&sector_data[..n]);
+            remaining -= n;
+        }
+
+        Ok(data)
+    }
+
+    fn get_minifat_entry(
+        &mut self,
+        mini_sector: u32,
+    ) -> Result<u32, &'static str> {
+        if self.mini_fat_sectors.is_empty() {
+            return Ok(ENDOFCHAIN);
+        }
+
+        let entries_per_sector = self.sector_size / 4;
+        let fat_sector_index = mini_sector as usize / entries_per_sector;
+        let sector = match self.mini_fat_sectors.get(fat_sector_index) {
+            Some(&sector) => sector,
+            None => return Ok(ENDOFCHAIN),
+        };
+        let fat = self.read_sector(sector)?;
+        let entry_offset = (mini_sector as usize % entries_per_sector) * 4;
+        Ok(u32::from_le_bytes(
+            fat[entry_offset..entry_offset + 4].try_into().unwrap(),
+        ))
+    }
+
+    fn get_mini_stream_data(
+        &mut self,
+        start_mini_sector: u32,
+        size: u64,
+    ) -> Result<Vec<u8>, &'static str> {
+        if self.mini_stream_size == 0 {
+            return Err("No mini stream present");
+        }
+
+        let mini_stream_data = self.get_regular_stream_data(
+            self.mini_stream_start,
+            self.mini_stream_size,
+        )?;
+
+        let mut chain = Vec::new();
+        let mut current = start_mini_sector;
+        while current < MAX_REGULAR_SECTOR {
+            if chain.contains(&current) {
+                break;
+            }
+            chain.push(current);
+            current = match self.get_minifat_entry(current) {
+                Ok(n) if n == ENDOFCHAIN => break,
+                Ok(n) => n,
+                Err(_) => break,
+            };
+        }
+
+        // Same clamping as `get_regular_stream_data`, but against mini
+        // sectors reachable within the already-bounded mini stream.
+        let reachable = chain.len() as u64 * self.mini_sector_size as u64;
+        let mut remaining = std::cmp::min(size, reachable) as usize;
+
+        let mut data = Vec::with_capacity(remaining);
+        for mini_sector in chain {
+            if remaining == 0 {
+                break;
+            }
+            let mini_offset = mini_sector as usize * self.mini_sector_size;
+            if mini_offset >= mini_stream_data.len() {
+                return Err("Mini stream offset out of range");
+            }
+            let n = std::cmp::min(
+                std::cmp::min(self.mini_sector_size, remaining),
+                mini_stream_data.len() - mini_offset,
+            );
+            data.extend_from_slice(
+                &mini_stream_data[mini_offset..mini_offset + n],
+            );
+            remaining -= n;
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an [`OLECFParser`] with the given private fields set directly,
+    /// bypassing header/directory parsing. Lets tests drive `follow_chain`,
+    /// `follow_difat_chain` and `walk_storage_tree` in isolation against
+    /// crafted, potentially malformed, chain data.
+    fn empty_parser(data: &[u8]) -> OLECFParser<'_> {
+        OLECFParser {
+            data,
+            sector_size: 512,
+            mini_sector_size: 64,
+            fat_sectors: Vec::new(),
+            directory_sectors: Vec::new(),
+            mini_fat_sectors: Vec::new(),
+            entries: Vec::new(),
+            dir_entries: HashMap::new(),
+            paths: HashMap::new(),
+            mini_stream_start: 0,
+            mini_stream_size: 0,
+        }
+    }
+
+    #[test]
+    fn follow_chain_terminates_on_cycle() {
+        // One header sector (unused) followed by a FAT sector at sector 0
+        // whose entries form a 0 -> 1 -> 0 cycle.
+        let mut data = vec![0u8; 512 + 512];
+        data[512..516].copy_from_slice(&1u32.to_le_bytes()); // entry 0 -> 1
+        data[516..520].copy_from_slice(&0u32.to_le_bytes()); // entry 1 -> 0
+
+        let mut parser = empty_parser(&data);
+        parser.fat_sectors = vec![0];
+
+        let chain = parser.follow_chain(0);
+        assert_eq!(chain, vec![0, 1]);
+    }
+
+    #[test]
+    fn follow_difat_chain_terminates_on_cycle() {
+        // Two DIFAT sectors (0 and 1, right after the header) whose "next"
+        // pointers form a cycle: sector 0 -> sector 1 -> sector 0. Every
+        // FAT-sector slot is FREESECT, so no FAT sectors should be
+        // collected -- the test is that this returns instead of looping
+        // forever.
+        let mut data = vec![0xFFu8; 512 + 512 + 512];
+        let entries_per_sector = 512 / 4 - 1;
+        data[512 + entries_per_sector * 4..512 + entries_per_sector * 4 + 4]
+            .copy_from_slice(&1u32.to_le_bytes());
+        data[1024 + entries_per_sector * 4..1024 + entries_per_sector * 4 + 4]
+            .copy_from_slice(&0u32.to_le_bytes());
+
+        let mut parser = empty_parser(&data);
+        parser.follow_difat_chain(0);
+
+        assert!(parser.fat_sectors.is_empty());
+    }
+
+    #[test]
+    fn walk_storage_tree_terminates_on_sibling_cycle() {
+        let data = vec![0u8; 0];
+        let mut parser = empty_parser(&data);
+        parser.entries = vec![
+            DirectoryEntry {
+                name: "a".to_string(),
+                size: 0,
+                start_sector: FREESECT,
+                stream_type: STREAM_TYPE,
+                left_sibling_id: 1,
+                right_sibling_id: NOSTREAM,
+                child_id: NOSTREAM,
+            },
+            DirectoryEntry {
+                name: "b".to_string(),
+                size: 0,
+                start_sector: FREESECT,
+                stream_type: STREAM_TYPE,
+                left_sibling_id: NOSTREAM,
+                // Points back at entry 0, forming a cycle.
+                right_sibling_id: 0,
+                child_id: NOSTREAM,
+            },
+        ];
+
+        let mut visited = Vec::new();
+        parser.walk_storage_tree(0, "", &mut visited);
+
+        assert_eq!(parser.paths.get("a"), Some(&0));
+        assert_eq!(parser.paths.get("b"), Some(&1));
+    }
+
+    /// Builds the fixed-size (76-byte) portion of a Compound File Header,
+    /// up to and including `difat_count`, with `sector_shift`/
+    /// `mini_sector_shift` set as given. This is all `parse_header` reads
+    /// before validating those two fields, so it's enough to exercise that
+    /// validation without a full, otherwise-valid file.
+    fn header_prefix(sector_shift: u16, mini_sector_shift: u16) -> Vec<u8> {
+        let mut h = vec![0u8; 76];
+        h[0..8].copy_from_slice(OLECF_SIGNATURE);
+        h[28..30].copy_from_slice(&0xFFFEu16.to_le_bytes());
+        h[30..32].copy_from_slice(&sector_shift.to_le_bytes());
+        h[32..34].copy_from_slice(&mini_sector_shift.to_le_bytes());
+        h[60..64].copy_from_slice(&FREESECT.to_le_bytes()); // first_mini_fat
+        h[68..72].copy_from_slice(&FREESECT.to_le_bytes()); // first_difat_sector
+        h
+    }
+
+    /// Builds a minimal, otherwise-valid compound file with the given
+    /// `sector_shift` (9 or 12): a header sector, one FAT sector
+    /// (declaring the directory sector as its own one-sector chain), and
+    /// one directory sector containing a single root entry with no
+    /// children.
+    fn minimal_olecf(sector_shift: u16) -> Vec<u8> {
+        let sector_size = 1usize << sector_shift;
+
+        let mut header = header_prefix(sector_shift, MINI_SECTOR_SHIFT);
+        header[44..48].copy_from_slice(&1u32.to_le_bytes()); // num_fat_sectors
+        header[48..52].copy_from_slice(&1u32.to_le_bytes()); // first_dir_sector
+        // First DIFAT entry (of 109 in the header) points at FAT sector 0;
+        // the rest are left as FREESECT, meaning "unused".
+        let mut difat = vec![0xFFu8; 109 * 4];
+        difat[0..4].copy_from_slice(&0u32.to_le_bytes());
+        header.extend_from_slice(&difat);
+        header.resize(sector_size, 0);
+
+        // FAT sector 0: entry 1 (the directory sector) ends its own chain.
+        let mut fat_sector = vec![0u8; sector_size];
+        fat_sector[4..8].copy_from_slice(&ENDOFCHAIN.to_le_bytes());
+
+        // Directory sector: a single root entry, no siblings or children.
+        let mut dir_sector = vec![0u8; sector_size];
+        let name_utf16: Vec<u8> = "Root Entry"
+            .encode_utf16()
+            .flat_map(|u| u.to_le_bytes())
+            .collect();
+        dir_sector[..name_utf16.len()].copy_from_slice(&name_utf16);
+        dir_sector[64..66]
+            .copy_from_slice(&((name_utf16.len() as u16) + 2).to_le_bytes());
+        dir_sector[66] = ROOT_STORAGE_TYPE;
+        dir_sector[68..72].copy_from_slice(&NOSTREAM.to_le_bytes());
+        dir_sector[72..76].copy_from_slice(&NOSTREAM.to_le_bytes());
+        dir_sector[76..80].copy_from_slice(&NOSTREAM.to_le_bytes());
+        dir_sector[116..120].copy_from_slice(&FREESECT.to_le_bytes());
+
+        [header, fat_sector, dir_sector].concat()
+    }
+
+    #[test]
+    fn sector_size_accepts_v3_and_v4_shifts() {
+        let v3 = OLECFParser::new(&minimal_olecf(9)).unwrap();
+        assert_eq!(v3.sector_size, 512);
+        assert!(v3.dir_entries.contains_key("Root Entry"));
+
+        let v4 = OLECFParser::new(&minimal_olecf(12)).unwrap();
+        assert_eq!(v4.sector_size, 4096);
+        assert!(v4.dir_entries.contains_key("Root Entry"));
+    }
+
+    #[test]
+    fn sector_size_rejects_other_shifts() {
+        assert!(OLECFParser::new(&header_prefix(10, MINI_SECTOR_SHIFT))
+            .is_err());
+        assert!(OLECFParser::new(&header_prefix(SECTOR_SHIFT, 7)).is_err());
+    }
+
+    #[test]
+    fn decompress_ovba_rejects_bad_signature() {
+        assert!(decompress_ovba(&[0x00, 0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn decompress_ovba_round_trips_uncompressed_chunk() {
+        // A single, uncompressed chunk holding the literal bytes "Hello":
+        // header = size_field(body_len - 1 = 4) | 0b011 << 12, bit 15 clear.
+        let data = [0x01, 0x04, 0x30, b'H', b'e', b'l', b'l', b'o'];
+        assert_eq!(decompress_ovba(&data).unwrap(), b"Hello");
+    }
+
+    #[test]
+    fn ovba_copy_token_bit_count_matches_spec_bounds() {
+        assert_eq!(ovba_copy_token_bit_count(0), 4);
+        assert_eq!(ovba_copy_token_bit_count(16), 4);
+        assert_eq!(ovba_copy_token_bit_count(17), 5);
+        assert_eq!(ovba_copy_token_bit_count(33), 6);
+        // Uncapped this would need 13 bits (2^13 = 8192 >= 5000), but the
+        // field is clamped to 12.
+        assert_eq!(ovba_copy_token_bit_count(5000), 12);
+    }
+}
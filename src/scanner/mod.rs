@@ -9,31 +9,225 @@ use crate::{modules, wasm};
 use bitvec::prelude::*;
 use bitvec::vec::BitVec;
 use memmap::MmapOptions;
+use protobuf::MessageDyn;
 
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::File;
 use std::path::Path;
 use std::ptr::null;
 use std::rc::Rc;
 use std::slice::Iter;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
 use wasmtime::{
-    Global, GlobalType, MemoryType, Mutability, Store, TypedFunc, Val, ValType,
+    Caller, Global, GlobalType, MemoryType, Mutability, Store, TypedFunc, Val,
+    ValType,
 };
 
+mod pool;
+
 #[cfg(test)]
 mod tests;
 
+pub use pool::{ScannerPool, ScannerPoolConfig};
+
+/// How often the epoch ticker increments the engine's epoch. The smaller
+/// this interval, the finer-grained (but less precise, due to scheduling
+/// jitter) [`Scanner::set_timeout`] durations can be.
+const EPOCH_TICK: Duration = Duration::from_millis(1);
+
+/// Largest size, in bytes, that the string pool's backing buffer is
+/// allowed to retain between scans. See [`BStringPool::reset`].
+const STRING_POOL_HIGH_WATER_MARK: usize = 1 << 20; // 1 MiB
+
+/// Error returned by [`Scanner::scan`] and [`Scanner::scan_file`].
+#[derive(Debug)]
+pub enum ScanError {
+    /// The scan was aborted because it took longer than the duration passed
+    /// to [`Scanner::set_timeout`].
+    Timeout,
+    /// A WebAssembly trap occurred while evaluating the compiled rules.
+    Wasm(anyhow::Error),
+    /// An I/O error occurred while reading the file passed to
+    /// [`Scanner::scan_file`].
+    Io(std::io::Error),
+    /// A module imported by the rules has no `main_fn`, and its output
+    /// wasn't supplied with [`Scanner::set_module_output`] (or the
+    /// `_proto` variant) before this scan.
+    MissingModuleOutput(String),
+    /// A module's output -- supplied by the user or produced by its
+    /// `main_fn` -- was a protobuf message of a different type than the
+    /// module's `root_struct_descriptor` declares.
+    ModuleOutputTypeMismatch {
+        module: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScanError::Timeout => write!(f, "scan timed out"),
+            ScanError::Wasm(err) => write!(f, "{}", err),
+            ScanError::Io(err) => write!(f, "{}", err),
+            ScanError::MissingModuleOutput(module) => write!(
+                f,
+                "module `{}` has no main function, its output must be \
+                 provided with `Scanner::set_module_output` before \
+                 calling `scan`",
+                module
+            ),
+            ScanError::ModuleOutputTypeMismatch { module, expected, found } => {
+                write!(
+                    f,
+                    "output of module `{}` must be `{}`, but it was `{}`",
+                    module, expected, found
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ScanError {}
+
+impl From<std::io::Error> for ScanError {
+    fn from(err: std::io::Error) -> Self {
+        ScanError::Io(err)
+    }
+}
+
+impl ScanError {
+    /// Builds a [`ScanError`] from the error returned by calling the
+    /// compiled rules' `main` function. If the error is caused by the
+    /// epoch deadline being reached, it's turned into [`ScanError::Timeout`],
+    /// any other trap is wrapped in [`ScanError::Wasm`].
+    fn from_wasm_error(err: anyhow::Error) -> Self {
+        match err.downcast_ref::<wasmtime::Trap>() {
+            Some(trap) if *trap == wasmtime::Trap::Interrupt => {
+                ScanError::Timeout
+            }
+            _ => ScanError::Wasm(err),
+        }
+    }
+}
+
+/// Error returned by [`Scanner::set_module_output`].
+#[derive(Debug)]
+pub enum SetModuleOutputError {
+    /// `module_name` doesn't match any of the built-in modules.
+    UnknownModule(String),
+    /// `data` couldn't be parsed as the module's expected output type.
+    Protobuf(protobuf::Error),
+}
+
+impl fmt::Display for SetModuleOutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetModuleOutputError::UnknownModule(name) => {
+                write!(f, "`{}` is not a known module", name)
+            }
+            SetModuleOutputError::Protobuf(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for SetModuleOutputError {}
+
+impl From<protobuf::Error> for SetModuleOutputError {
+    fn from(err: protobuf::Error) -> Self {
+        SetModuleOutputError::Protobuf(err)
+    }
+}
+
+/// Periodically increments the epoch of the [`wasmtime::Engine`] that a
+/// [`Scanner`] was built on, so that scans with a configured timeout get
+/// interrupted. A single ticker is shared by all the scans performed by a
+/// given [`Scanner`], and is only started the first time
+/// [`Scanner::set_timeout`] is called. The background thread is stopped
+/// when the ticker (and therefore the owning [`Scanner`]) is dropped.
+///
+/// The ticker must increment the epoch of the same `Engine` the `Scanner`'s
+/// `Store` was created with: each `Engine` has its own independent epoch
+/// counter, so a ticker incrementing the wrong one would never trigger the
+/// deadline armed on the `Scanner`'s store. This matters in particular for
+/// [`ScannerPool`], whose scanners run on a dedicated pooling-allocator
+/// engine rather than the default [`wasm::ENGINE`].
+struct EpochTicker {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl EpochTicker {
+    fn new(engine: wasmtime::Engine) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_flag = stop.clone();
+        let handle = std::thread::spawn(move || {
+            while !stop_flag.load(Ordering::Relaxed) {
+                std::thread::sleep(EPOCH_TICK);
+                engine.increment_epoch();
+            }
+        });
+        Self { stop, handle: Some(handle) }
+    }
+}
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
 /// Scans data with already compiled YARA rules.
 pub struct Scanner<'r> {
     wasm_store: wasmtime::Store<ScanContext<'r>>,
     wasm_main_fn: TypedFunc<(), ()>,
     filesize: wasmtime::Global,
+    /// The engine that `wasm_store` was created with. Kept around (wasmtime
+    /// engines are a cheap `Clone`, internally reference-counted) so that
+    /// [`EpochTicker`] can be created, on demand, ticking the right engine.
+    engine: wasmtime::Engine,
+    /// Number of epoch ticks that a scan is allowed to run for before it's
+    /// interrupted with [`ScanError::Timeout`]. `None` means no timeout.
+    timeout_ticks: Option<u64>,
+    /// Background thread that increments `engine`'s epoch. Lazily created
+    /// the first time [`Scanner::set_timeout`] is called.
+    ticker: Option<EpochTicker>,
+    /// Protobuf messages set with [`Scanner::set_module_output`] for
+    /// modules that don't have a `main_fn` and therefore can't produce
+    /// their output on their own. An entry is removed when a
+    /// [`Scanner::scan`] consumes it, which happens only for modules that
+    /// the rules import and that have no `main_fn`; other entries stay.
+    module_outputs: HashMap<String, Box<dyn MessageDyn>>,
 }
 
 impl<'r> Scanner<'r> {
     /// Creates a new scanner.
     pub fn new(compiled_rules: &'r CompiledRules) -> Self {
+        Self::new_with_engine(&crate::wasm::ENGINE, compiled_rules)
+            .expect("failed to instantiate scanner on the default engine")
+    }
+
+    /// Creates a new scanner that instantiates its wasm module on `engine`,
+    /// instead of the default [`wasm::ENGINE`]. This is used by
+    /// [`ScannerPool`] to hand out handles backed by a pooling-allocator
+    /// engine, while [`Scanner::new`] keeps using the regular engine.
+    ///
+    /// Returns an error if `engine` can't instantiate the compiled wasm
+    /// module, which for a pooling-allocator engine also happens once its
+    /// configured instance/memory capacity is exhausted.
+    pub(crate) fn new_with_engine(
+        engine: &wasmtime::Engine,
+        compiled_rules: &'r CompiledRules,
+    ) -> anyhow::Result<Self> {
         let mut wasm_store = Store::new(
-            &crate::wasm::ENGINE,
+            engine,
             ScanContext {
                 compiled_rules,
                 string_pool: BStringPool::new(),
@@ -42,12 +236,14 @@ impl<'r> Scanner<'r> {
                 scanned_data: null(),
                 scanned_data_len: 0,
                 rules_matching: Vec::new(),
+                rules_matching_high_water: 0,
                 rules_matching_bitmap: BitVec::repeat(
                     false,
                     compiled_rules.rules().len(),
                 ),
                 main_memory: None,
                 lookup_stack_top: None,
+                pattern_matches: HashMap::new(),
             },
         );
 
@@ -66,13 +262,17 @@ impl<'r> Scanner<'r> {
         )
         .unwrap();
 
+        // Unlike the globals above, this can fail once a pooling-allocator
+        // engine's `max_memories` capacity is exhausted, so it's propagated
+        // to the caller instead of unwrapped.
         let main_memory =
-            wasmtime::Memory::new(&mut wasm_store, MemoryType::new(1, None))
-                .unwrap();
+            wasmtime::Memory::new(&mut wasm_store, MemoryType::new(1, None))?;
 
         // Instantiate the module. This takes the wasm code provided by the
         // `compiled_wasm_mod` function and links its imported functions with
-        // the implementations that YARA provides (see wasm.rs).
+        // the implementations that YARA provides (see wasm.rs). Like
+        // `Memory::new` above, this can fail once a pooling-allocator
+        // engine's `max_instances` capacity is exhausted.
         let wasm_instance = wasm::new_linker()
             .define("yr", "filesize", filesize)
             .unwrap()
@@ -80,8 +280,62 @@ impl<'r> Scanner<'r> {
             .unwrap()
             .define("yr", "main_memory", main_memory)
             .unwrap()
-            .instantiate(&mut wasm_store, compiled_rules.compiled_wasm_mod())
-            .unwrap();
+            .func_wrap(
+                "yr",
+                "pattern_match",
+                |mut caller: Caller<'_, ScanContext<'r>>,
+                 rule_id: i32,
+                 pattern_id: u32,
+                 offset: i64,
+                 length: i32|
+                 -> anyhow::Result<()> {
+                    let ctx = caller.data();
+
+                    // `offset`/`length` come from wasm-compiled rule
+                    // bytecode, not from trusted Rust code, so they must be
+                    // validated against `scanned_data_len` before they're
+                    // used to build a slice: a negative `offset` wraps to a
+                    // huge `usize` on cast, and nothing on the wasm side
+                    // guarantees `offset + length` stays within bounds.
+                    let in_bounds = offset >= 0
+                        && usize::try_from(offset)
+                            .ok()
+                            .and_then(|offset| {
+                                offset.checked_add(length as usize)
+                            })
+                            .is_some_and(|end| end <= ctx.scanned_data_len);
+
+                    if !in_bounds {
+                        return Err(anyhow::anyhow!(
+                            "pattern_match: offset {} length {} out of \
+                             bounds for {} bytes of scanned data",
+                            offset,
+                            length,
+                            ctx.scanned_data_len,
+                        ));
+                    }
+
+                    let identifier =
+                        ctx.string_pool.get(pattern_id).to_string();
+                    // Safety: bounds were validated above, and
+                    // `scanned_data` is valid for the duration of this call.
+                    let matched_bytes = unsafe {
+                        std::slice::from_raw_parts(
+                            ctx.scanned_data.add(offset as usize),
+                            length as usize,
+                        )
+                    };
+                    caller.data_mut().track_pattern_match(
+                        rule_id as RuleId,
+                        &identifier,
+                        offset as usize,
+                        matched_bytes,
+                    );
+                    Ok(())
+                },
+            )
+            .unwrap()
+            .instantiate(&mut wasm_store, compiled_rules.compiled_wasm_mod())?;
 
         // Obtain a reference to the "main" function exported by the module.
         let wasm_main_fn = wasm_instance
@@ -91,21 +345,109 @@ impl<'r> Scanner<'r> {
         wasm_store.data_mut().main_memory = Some(main_memory);
         wasm_store.data_mut().lookup_stack_top = Some(lookup_stack_top);
 
-        Self { wasm_store, wasm_main_fn, filesize }
+        // Arm the epoch deadline with a single tick. As long as nobody calls
+        // `set_timeout` the engine's epoch is never incremented, so this
+        // deadline is never reached and scans run without a time limit.
+        wasm_store.set_epoch_deadline(1);
+        wasm_store.epoch_deadline_trap();
+
+        Ok(Self {
+            wasm_store,
+            wasm_main_fn,
+            filesize,
+            engine: engine.clone(),
+            timeout_ticks: None,
+            ticker: None,
+            module_outputs: HashMap::new(),
+        })
+    }
+
+    /// Sets the output for a module that doesn't have a `main_fn`, as a
+    /// serialized protocol buffer.
+    ///
+    /// Some modules don't parse any data on their own; instead, they expect
+    /// the caller to supply their output (for example, a pre-parsed
+    /// structured report produced by an external tool). `data` must be the
+    /// serialization, using the module's own `.proto` schema, of the
+    /// message that the module expects. It's deserialized right away, and
+    /// consumed the next time [`Scanner::scan`] (or [`Scanner::scan_file`])
+    /// runs.
+    ///
+    /// Returns an error if `module_name` is not a known module, or if
+    /// `data` can't be parsed as the module's output type.
+    pub fn set_module_output(
+        &mut self,
+        module_name: &str,
+        data: &[u8],
+    ) -> Result<&mut Self, SetModuleOutputError> {
+        let module = modules::BUILTIN_MODULES.get(module_name).ok_or_else(
+            || SetModuleOutputError::UnknownModule(module_name.to_string()),
+        )?;
+
+        let mut msg = module.root_struct_descriptor.new_instance();
+        msg.merge_from_bytes_dyn(data)?;
+
+        self.module_outputs.insert(module_name.to_string(), msg);
+
+        Ok(self)
+    }
+
+    /// Sets the output for a module that doesn't have a `main_fn`, as an
+    /// already-parsed protobuf message.
+    ///
+    /// This is the typed counterpart of [`Scanner::set_module_output`], for
+    /// callers that already have a [`MessageDyn`] at hand instead of its
+    /// serialized bytes. `data`'s type must match the module's
+    /// `root_struct_descriptor`.
+    pub fn set_module_output_proto(
+        &mut self,
+        module_name: &str,
+        data: Box<dyn MessageDyn>,
+    ) -> &mut Self {
+        self.module_outputs.insert(module_name.to_string(), data);
+        self
+    }
+
+    /// Sets a timeout for scan operations.
+    ///
+    /// Once the timeout expires, any scan in progress is aborted and
+    /// [`Scanner::scan`] (or [`Scanner::scan_file`]) returns
+    /// [`ScanError::Timeout`]. The timeout is enforced by incrementing the
+    /// wasm engine's epoch from a background thread that is started the
+    /// first time this function is called, and stopped when the `Scanner`
+    /// is dropped.
+    pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+        let ticks = std::cmp::max(
+            1,
+            (timeout.as_nanos() / EPOCH_TICK.as_nanos()) as u64,
+        );
+        self.timeout_ticks = Some(ticks);
+        let engine = &self.engine;
+        self.ticker
+            .get_or_insert_with(|| EpochTicker::new(engine.clone()));
+        self
     }
 
     /// Scans a file.
     pub fn scan_file<'s, P: AsRef<Path>>(
         &'s mut self,
         path: P,
-    ) -> std::io::Result<ScanResults<'s, 'r>> {
+    ) -> Result<ScanResults<'s, 'r>, ScanError> {
         let file = File::open(path)?;
         let mmap = unsafe { MmapOptions::new().map(&file)? };
-        Ok(self.scan(&mmap[..]))
+        self.scan(&mmap[..])
     }
 
     /// Scans a data buffer.
-    pub fn scan<'s>(&'s mut self, data: &[u8]) -> ScanResults<'s, 'r> {
+    pub fn scan<'s>(
+        &'s mut self,
+        data: &[u8],
+    ) -> Result<ScanResults<'s, 'r>, ScanError> {
+        // Re-arm the epoch deadline for this scan, relative to the engine's
+        // current epoch.
+        self.wasm_store
+            .set_epoch_deadline(self.timeout_ticks.unwrap_or(u64::MAX));
+
         // Set the global variable `filesize` to the size of the scanned data.
         self.filesize
             .set(&mut self.wasm_store, Val::I64(data.len() as i64))
@@ -114,12 +456,24 @@ impl<'r> Scanner<'r> {
         let ctx = self.wasm_store.data_mut();
 
         ctx.rules_matching_bitmap.fill(false);
+
+        // Remember the largest number of matching rules seen so far, so
+        // that `rules_matching` can be pre-sized for the next scan instead
+        // of growing from scratch every time.
+        ctx.rules_matching_high_water =
+            ctx.rules_matching_high_water.max(ctx.rules_matching.len());
         ctx.rules_matching.clear();
+        ctx.rules_matching.reserve(ctx.rules_matching_high_water);
+        ctx.pattern_matches.clear();
+
         ctx.scanned_data = data.as_ptr();
         ctx.scanned_data_len = data.len();
 
-        // TODO: this should be done only if the string pool is too large.
-        ctx.string_pool = BStringPool::new();
+        // Reset the string pool instead of rebuilding it from scratch, so
+        // that steady-state scanning doesn't allocate. A pool that grew
+        // unusually large on some previous scan is still released, rather
+        // than held onto for the `Scanner`'s entire lifetime.
+        ctx.string_pool.reset(STRING_POOL_HIGH_WATER_MARK);
 
         for module_name in ctx.compiled_rules.imported_modules() {
             // Lookup the module in the list of built-in modules.
@@ -132,22 +486,42 @@ impl<'r> Scanner<'r> {
             let module_output = if let Some(main_fn) = module.main_fn {
                 main_fn(ctx)
             } else {
-                // Implement the case in which the module doesn't have a main
-                // function and the serialized data should be provided by the
-                // user.
-                todo!()
+                // This module doesn't have a main function, so its output
+                // must have been provided by the user with
+                // `Scanner::set_module_output` (or the `_proto` variant)
+                // before calling `scan`.
+                match self.module_outputs.remove(module_name) {
+                    Some(output) => output,
+                    None => {
+                        ctx.scanned_data = null();
+                        ctx.scanned_data_len = 0;
+                        return Err(ScanError::MissingModuleOutput(
+                            module_name.to_string(),
+                        ));
+                    }
+                }
             };
 
-            // Make sure that the module is returning a protobuf message of the
-            // expected type.
-            debug_assert_eq!(
-                module_output.descriptor_dyn().full_name(),
-                module.root_struct_descriptor.full_name(),
-                "main function of module `{}` must return `{}`, but returned `{}`",
-                module_name,
-                module.root_struct_descriptor.full_name(),
-                module_output.descriptor_dyn().full_name(),
-            );
+            // Make sure that the module is returning a protobuf message of
+            // the expected type. Checked unconditionally -- not just via
+            // debug_assert_eq!, which release builds compile out -- since
+            // a user-supplied `set_module_output`/`set_module_output_proto`
+            // call can hand us any message type it likes.
+            if module_output.descriptor_dyn().full_name()
+                != module.root_struct_descriptor.full_name()
+            {
+                let expected =
+                    module.root_struct_descriptor.full_name().to_string();
+                let found =
+                    module_output.descriptor_dyn().full_name().to_string();
+                ctx.scanned_data = null();
+                ctx.scanned_data_len = 0;
+                return Err(ScanError::ModuleOutputTypeMismatch {
+                    module: module_name.to_string(),
+                    expected,
+                    found,
+                });
+            }
 
             // When compile-time optimizations are enabled we don't need to
             // generate structure fields for enums. This is because during the
@@ -180,7 +554,7 @@ impl<'r> Scanner<'r> {
         }
 
         // Invoke the main function.
-        self.wasm_main_fn.call(&mut self.wasm_store, ()).unwrap();
+        let result = self.wasm_main_fn.call(&mut self.wasm_store, ());
 
         let ctx = self.wasm_store.data_mut();
 
@@ -189,7 +563,9 @@ impl<'r> Scanner<'r> {
         ctx.scanned_data = null();
         ctx.scanned_data_len = 0;
 
-        ScanResults::new(self.wasm_store.data())
+        result.map_err(ScanError::from_wasm_error)?;
+
+        Ok(ScanResults::new(self.wasm_store.data()))
     }
 }
 
@@ -229,11 +605,70 @@ impl<'s, 'r> IterMatches<'s, 'r> {
 }
 
 impl<'s, 'r> Iterator for IterMatches<'s, 'r> {
-    type Item = &'r CompiledRule;
+    type Item = MatchingRule<'s, 'r>;
 
     fn next(&mut self) -> Option<Self::Item> {
         let rule_id = *self.iterator.next()?;
-        Some(&self.ctx.compiled_rules.rules()[rule_id as usize])
+        Some(MatchingRule { ctx: self.ctx, rule_id })
+    }
+}
+
+/// A rule that matched, together with the locations where its patterns
+/// matched in the scanned data.
+///
+/// Returned by [`IterMatches`], which is obtained from
+/// [`ScanResults::iter`].
+pub struct MatchingRule<'s, 'r> {
+    ctx: &'s ScanContext<'r>,
+    rule_id: RuleId,
+}
+
+impl<'s, 'r> MatchingRule<'s, 'r> {
+    /// The rule that matched.
+    pub fn rule(&self) -> &'r CompiledRule {
+        &self.ctx.compiled_rules.rules()[self.rule_id as usize]
+    }
+
+    /// Returns the locations where this rule's patterns matched: for each
+    /// match, its pattern identifier (e.g. `$a`), the offset within the
+    /// scanned data, and the matched bytes themselves.
+    pub fn matching_strings(
+        &self,
+    ) -> impl Iterator<Item = &'s PatternMatch> + 's {
+        self.ctx
+            .pattern_matches
+            .get(&self.rule_id)
+            .into_iter()
+            .flat_map(|matches| matches.iter())
+    }
+}
+
+/// A single match of one of a rule's patterns against the scanned data.
+pub struct PatternMatch {
+    identifier: String,
+    offset: usize,
+    matched_bytes: Box<[u8]>,
+}
+
+impl PatternMatch {
+    /// The pattern's identifier (e.g. `$a`, `$foo`).
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// Offset, within the scanned data, where the match starts.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Length, in bytes, of the match.
+    pub fn matched_length(&self) -> usize {
+        self.matched_bytes.len()
+    }
+
+    /// The bytes that matched.
+    pub fn matched_bytes(&self) -> &[u8] {
+        &self.matched_bytes
     }
 }
 
@@ -268,6 +703,10 @@ pub(crate) struct ScanContext<'r> {
     pub(crate) rules_matching_bitmap: BitVec,
     /// Vector containing the IDs of the rules that matched.
     pub(crate) rules_matching: Vec<RuleId>,
+    /// Largest value that `rules_matching.len()` has reached across scans
+    /// performed with this context, used to pre-size `rules_matching` for
+    /// the next scan instead of growing it from scratch.
+    pub(crate) rules_matching_high_water: usize,
     /// Data being scanned.
     pub(crate) scanned_data: *const u8,
     /// Length of data being scanned.
@@ -287,4 +726,28 @@ pub(crate) struct ScanContext<'r> {
     /// Module's main memory.
     pub(crate) main_memory: Option<wasmtime::Memory>,
     pub(crate) lookup_stack_top: Option<wasmtime::Global>,
+    /// For each rule that matched, the locations where its patterns
+    /// matched the scanned data. Populated as patterns match, while
+    /// `scanned_data` is still valid.
+    pub(crate) pattern_matches: HashMap<RuleId, Vec<PatternMatch>>,
+}
+
+impl<'r> ScanContext<'r> {
+    /// Records that pattern `identifier`, belonging to `rule_id`, matched
+    /// at `offset` within the scanned data. The matched bytes are copied
+    /// out immediately, so they remain accessible from [`ScanResults`]
+    /// even after `scanned_data` is reset to null at the end of the scan.
+    pub(crate) fn track_pattern_match(
+        &mut self,
+        rule_id: RuleId,
+        identifier: &str,
+        offset: usize,
+        matched_bytes: &[u8],
+    ) {
+        self.pattern_matches.entry(rule_id).or_default().push(PatternMatch {
+            identifier: identifier.to_string(),
+            offset,
+            matched_bytes: matched_bytes.to_vec().into_boxed_slice(),
+        });
+    }
 }
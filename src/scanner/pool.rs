@@ -0,0 +1,100 @@
+/*! A pool of reusable [`Scanner`] handles backed by wasmtime's pooling
+instance allocator.
+
+*/
+
+use wasmtime::{
+    Config, Engine, InstanceAllocationStrategy, PoolingAllocationConfig,
+};
+
+use crate::compiler::CompiledRules;
+
+use super::Scanner;
+
+/// Configuration options for a [`ScannerPool`].
+#[derive(Clone, Copy, Debug)]
+pub struct ScannerPoolConfig {
+    /// Maximum number of wasm instances the pool pre-reserves memory for.
+    /// This bounds the number of [`Scanner`] handles that can be backed by
+    /// pooled instances at the same time.
+    pub max_instances: u32,
+    /// Maximum number of wasm linear memories the pool pre-reserves. This
+    /// bounds the total amount of memory the pool can use.
+    pub max_memories: u32,
+}
+
+impl Default for ScannerPoolConfig {
+    fn default() -> Self {
+        Self { max_instances: 32, max_memories: 32 }
+    }
+}
+
+/// A pool of [`Scanner`] handles backed by a wasmtime engine configured with
+/// the pooling instance allocator, instead of the on-demand allocator that
+/// [`Scanner::new`] uses.
+///
+/// Every call to [`ScannerPool::get`] still builds a linker and instantiates
+/// the compiled wasm module from scratch, exactly like [`Scanner::new`]
+/// does; there's no free list of previously-returned `Scanner`s to recycle.
+/// What the pooling allocator buys is a fixed, pre-reserved memory budget
+/// for those instances (bounded by [`ScannerPoolConfig`]) instead of
+/// growing on demand, and once that budget is exhausted, `get` returns an
+/// error rather than letting the process's memory use grow unbounded.
+///
+/// A `ScannerPool` owns a [`CompiledRules`] reference and a wasmtime
+/// [`Engine`] configured with [`InstanceAllocationStrategy::Pooling`].
+/// Typical usage is to create one pool per set of compiled rules and call
+/// [`ScannerPool::get`] once per worker thread (for example, from a
+/// `rayon` parallel iterator scanning the files of a directory), reusing
+/// the returned [`Scanner`] across many calls to [`Scanner::scan`].
+pub struct ScannerPool<'r> {
+    engine: Engine,
+    rules: &'r CompiledRules,
+}
+
+impl<'r> ScannerPool<'r> {
+    /// Creates a new pool for scanning with `rules`, using the default
+    /// [`ScannerPoolConfig`].
+    pub fn new(rules: &'r CompiledRules) -> Self {
+        Self::with_config(rules, ScannerPoolConfig::default())
+    }
+
+    /// Creates a new pool for scanning with `rules`, configured according
+    /// to `config`.
+    pub fn with_config(
+        rules: &'r CompiledRules,
+        config: ScannerPoolConfig,
+    ) -> Self {
+        let mut pooling_config = PoolingAllocationConfig::default();
+        pooling_config.total_core_instances(config.max_instances);
+        pooling_config.total_memories(config.max_memories);
+
+        let mut engine_config = Config::new();
+        engine_config
+            .allocation_strategy(InstanceAllocationStrategy::Pooling(
+                pooling_config,
+            ))
+            .epoch_interruption(true);
+
+        let engine = Engine::new(&engine_config)
+            .expect("failed to create pooling-allocator wasmtime engine");
+
+        Self { engine, rules }
+    }
+
+    /// Builds a new [`Scanner`] instantiated on this pool's engine.
+    ///
+    /// Each handle is meant to be used from a single thread at a time (for
+    /// instance, one per worker thread in a parallel scan), and can be
+    /// reused across many calls to [`Scanner::scan`] — the scan context
+    /// (string pool, match bitmap, etc.) is recycled between scans rather
+    /// than being dropped and rebuilt, see
+    /// [`ScanContext`](super::ScanContext).
+    ///
+    /// Returns an error once this pool's [`ScannerPoolConfig::max_instances`]
+    /// or [`ScannerPoolConfig::max_memories`] capacity has already been
+    /// handed out to other live `Scanner`s.
+    pub fn get(&self) -> anyhow::Result<Scanner<'r>> {
+        Scanner::new_with_engine(&self.engine, self.rules)
+    }
+}
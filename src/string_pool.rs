@@ -0,0 +1,132 @@
+/*! A pool for interning byte strings produced at scan time.
+
+*/
+
+use bstr::BStr;
+use std::marker::PhantomData;
+
+/// A pool of byte strings, referenced by a small integer identifier.
+///
+/// Runtime values produced by YARA modules (for example, a string field
+/// returned by a module's `main` function) are interned here instead of
+/// being stored as separate, individually-allocated `BString`s. This keeps
+/// all the bytes produced during a scan in a single contiguous buffer,
+/// which is both cheaper to allocate and, via [`BStringPool::reset`],
+/// cheap to reuse across scans.
+pub(crate) struct BStringPool<Id> {
+    /// All the interned bytes, back-to-back.
+    buf: Vec<u8>,
+    /// Start offset and length, in `buf`, of each interned string. The
+    /// position of an entry in this vector is the string's `Id`.
+    index: Vec<(usize, usize)>,
+    _marker: PhantomData<Id>,
+}
+
+impl<Id> BStringPool<Id>
+where
+    Id: From<u32> + Into<u32> + Copy,
+{
+    /// Creates a new, empty pool.
+    pub fn new() -> Self {
+        Self { buf: Vec::new(), index: Vec::new(), _marker: PhantomData }
+    }
+
+    /// Interns `s`, copying its bytes into the pool, and returns the `Id`
+    /// that can later be used to retrieve it with [`BStringPool::get`].
+    pub fn get_or_intern<S: AsRef<[u8]>>(&mut self, s: S) -> Id {
+        let s = s.as_ref();
+        let start = self.buf.len();
+        self.buf.extend_from_slice(s);
+        let id = self.index.len() as u32;
+        self.index.push((start, s.len()));
+        Id::from(id)
+    }
+
+    /// Returns the byte string identified by `id`.
+    ///
+    /// Panics if `id` wasn't returned by a previous call to
+    /// [`BStringPool::get_or_intern`] on this pool, or if the pool has
+    /// been [`reset`](BStringPool::reset) since then.
+    pub fn get(&self, id: Id) -> &BStr {
+        let (start, len) = self.index[Into::<u32>::into(id) as usize];
+        BStr::new(&self.buf[start..start + len])
+    }
+
+    /// Clears the pool so that it can be reused by the next scan.
+    ///
+    /// All previously interned strings (and the `Id`s that identify them)
+    /// become invalid. The backing buffer's capacity is retained, unless
+    /// it exceeds `high_water_mark` bytes, in which case it's dropped and
+    /// a fresh, smaller buffer takes its place. This lets a pool that
+    /// grew unusually large for one scan shrink back down, instead of
+    /// holding on to that memory for the lifetime of the `Scanner`.
+    pub fn reset(&mut self, high_water_mark: usize) {
+        self.index.clear();
+        if self.buf.capacity() > high_water_mark {
+            self.buf = Vec::new();
+        } else {
+            self.buf.clear();
+        }
+    }
+}
+
+impl<Id> Default for BStringPool<Id>
+where
+    Id: From<u32> + Into<u32> + Copy,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_or_intern_round_trips() {
+        let mut pool: BStringPool<u32> = BStringPool::new();
+        let foo = pool.get_or_intern("foo");
+        let bar = pool.get_or_intern(b"bar".to_vec());
+
+        assert_eq!(pool.get(foo), "foo");
+        assert_eq!(pool.get(bar), "bar");
+    }
+
+    #[test]
+    fn get_or_intern_returns_distinct_ids_for_duplicate_strings() {
+        let mut pool: BStringPool<u32> = BStringPool::new();
+        let first = pool.get_or_intern("dup");
+        let second = pool.get_or_intern("dup");
+
+        assert_ne!(first, second);
+        assert_eq!(pool.get(first), "dup");
+        assert_eq!(pool.get(second), "dup");
+    }
+
+    #[test]
+    fn reset_invalidates_ids_but_keeps_capacity_below_high_water_mark() {
+        let mut pool: BStringPool<u32> = BStringPool::new();
+        pool.get_or_intern("hello");
+        let capacity_before = pool.buf.capacity();
+
+        pool.reset(capacity_before);
+
+        assert!(pool.index.is_empty());
+        assert_eq!(pool.buf.capacity(), capacity_before);
+
+        let id = pool.get_or_intern("world");
+        assert_eq!(pool.get(id), "world");
+    }
+
+    #[test]
+    fn reset_drops_buffer_above_high_water_mark() {
+        let mut pool: BStringPool<u32> = BStringPool::new();
+        pool.get_or_intern("a somewhat long string to grow the buffer");
+        assert!(pool.buf.capacity() > 0);
+
+        pool.reset(0);
+
+        assert_eq!(pool.buf.capacity(), 0);
+    }
+}